@@ -0,0 +1,388 @@
+//! A bounded, lock-free multi-producer multi-consumer channel.
+//!
+//! Unlike [`shared_channel`], whose [`SharedReceiver`] serializes every
+//! `recv` behind a single `Mutex<Receiver<T>>`, this module backs the
+//! channel with a bounded ring buffer based on Dmitry Vyukov's MPMC queue,
+//! so producers and consumers make progress without taking a lock. This
+//! trades the unbounded buffering of [`shared_channel`] for a fixed
+//! `capacity` (rounded up to a power of two) in exchange for scaling better
+//! under many concurrent receivers.
+//!
+//! [`shared_channel`]: ../fn.shared_channel.html
+//! [`SharedReceiver`]: ../struct.SharedReceiver.html
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{RecvError, SendError, TryRecvError, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Number of busy-spin polls to try before backing off to `thread::yield_now`.
+const SPIN_LIMIT: u32 = 50;
+/// Number of `thread::yield_now` polls to try before backing off to sleeping.
+const YIELD_LIMIT: u32 = 100;
+/// Upper bound on the sleep used while backing off between poll rounds.
+const MAX_BACKOFF: Duration = Duration::from_millis(4);
+
+/// Spins, then yields, then sleeps with a capped backoff, advancing `round`
+/// on every call. Shared by [`BoundedReceiver::recv`] and
+/// [`BoundedSender::send`] so both sides degrade the same way while waiting.
+fn backoff(round: &mut u32) {
+    if *round < SPIN_LIMIT {
+        // busy spin
+    } else if *round < YIELD_LIMIT {
+        thread::yield_now();
+    } else {
+        let sleep = Duration::from_micros(u64::from(*round - YIELD_LIMIT) * 100);
+        thread::sleep(sleep.min(MAX_BACKOFF));
+    }
+    *round += 1;
+}
+
+struct Cell<T> {
+    sequence: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+struct Queue<T> {
+    buffer: Vec<Cell<T>>,
+    mask: usize,
+    enqueue_pos: AtomicUsize,
+    dequeue_pos: AtomicUsize,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+}
+
+// `Cell::value` is only ever touched while holding exclusive access to that
+// slot (granted by the sequence-number handshake below), so sharing `Queue`
+// across threads is sound even though `UnsafeCell` is itself `!Sync`.
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T> Queue<T> {
+    fn with_capacity(capacity: usize) -> Queue<T> {
+        let capacity = capacity.max(1).next_power_of_two();
+        let buffer = (0..capacity)
+            .map(|i| Cell {
+                sequence: AtomicUsize::new(i),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+        Queue {
+            buffer,
+            mask: capacity - 1,
+            enqueue_pos: AtomicUsize::new(0),
+            dequeue_pos: AtomicUsize::new(0),
+            senders: AtomicUsize::new(0),
+            receivers: AtomicUsize::new(0),
+        }
+    }
+
+    fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        if self.receivers.load(Ordering::SeqCst) == 0 {
+            return Err(TrySendError::Disconnected(value));
+        }
+        let mut pos = self.enqueue_pos.load(Ordering::Relaxed);
+        let mut value = Some(value);
+        loop {
+            let cell = &self.buffer[pos & self.mask];
+            let seq = cell.sequence.load(Ordering::Acquire);
+            let diff = seq as isize - pos as isize;
+            if diff == 0 {
+                if self
+                    .enqueue_pos
+                    .compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    unsafe {
+                        (*cell.value.get()).write(value.take().unwrap());
+                    }
+                    cell.sequence.store(pos + 1, Ordering::Release);
+                    return Ok(());
+                }
+                pos = self.enqueue_pos.load(Ordering::Relaxed);
+            } else if diff < 0 {
+                let value = value.take().unwrap();
+                return if self.receivers.load(Ordering::SeqCst) == 0 {
+                    Err(TrySendError::Disconnected(value))
+                } else {
+                    Err(TrySendError::Full(value))
+                };
+            } else {
+                pos = self.enqueue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut pos = self.dequeue_pos.load(Ordering::Relaxed);
+        loop {
+            let cell = &self.buffer[pos & self.mask];
+            let seq = cell.sequence.load(Ordering::Acquire);
+            let diff = seq as isize - (pos as isize + 1);
+            if diff == 0 {
+                if self
+                    .dequeue_pos
+                    .compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    let value = unsafe { (*cell.value.get()).assume_init_read() };
+                    cell.sequence
+                        .store(pos + self.buffer.len(), Ordering::Release);
+                    return Ok(value);
+                }
+                pos = self.dequeue_pos.load(Ordering::Relaxed);
+            } else if diff < 0 {
+                return if self.senders.load(Ordering::SeqCst) == 0 {
+                    Err(TryRecvError::Disconnected)
+                } else {
+                    Err(TryRecvError::Empty)
+                };
+            } else {
+                pos = self.dequeue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        let mut pos = *self.dequeue_pos.get_mut();
+        let end = *self.enqueue_pos.get_mut();
+        while pos != end {
+            let cell = &mut self.buffer[pos & self.mask];
+            unsafe {
+                (*cell.value.get()).assume_init_drop();
+            }
+            pos += 1;
+        }
+    }
+}
+
+/// The sending half of a [`bounded_shared_channel`].
+///
+/// Cloneable so multiple producers can share one channel, mirroring
+/// `std::sync::mpsc::Sender`.
+///
+/// [`bounded_shared_channel`]: fn.bounded_shared_channel.html
+pub struct BoundedSender<T> {
+    queue: Arc<Queue<T>>,
+}
+
+impl<T> BoundedSender<T> {
+    /// Sends a value, blocking (with a capped spin/yield/sleep backoff)
+    /// while the ring buffer is full.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut value = value;
+        let mut round = 0;
+        loop {
+            match self.queue.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(v)) => return Err(SendError(v)),
+                Err(TrySendError::Full(v)) => {
+                    value = v;
+                    backoff(&mut round);
+                }
+            }
+        }
+    }
+
+    /// Sends a value without blocking, failing with `TrySendError::Full` if
+    /// the ring buffer has no free slot right now.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.queue.try_send(value)
+    }
+}
+
+impl<T> Clone for BoundedSender<T> {
+    fn clone(&self) -> Self {
+        self.queue.senders.fetch_add(1, Ordering::SeqCst);
+        BoundedSender {
+            queue: Arc::clone(&self.queue),
+        }
+    }
+}
+
+impl<T> Drop for BoundedSender<T> {
+    fn drop(&mut self) {
+        self.queue.senders.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The receiving half of a [`bounded_shared_channel`].
+///
+/// Cloneable so multiple consumers can share one channel, preserving the
+/// API shape of [`SharedReceiver`] while dropping its internal lock.
+///
+/// [`bounded_shared_channel`]: fn.bounded_shared_channel.html
+/// [`SharedReceiver`]: ../struct.SharedReceiver.html
+pub struct BoundedReceiver<T> {
+    queue: Arc<Queue<T>>,
+}
+
+pub struct Iter<'a, T: 'a> {
+    rx: &'a BoundedReceiver<T>,
+}
+
+pub struct TryIter<'a, T: 'a> {
+    rx: &'a BoundedReceiver<T>,
+}
+
+impl<T> BoundedReceiver<T> {
+    /// Receives a value without blocking, failing with `TryRecvError::Empty`
+    /// if the ring buffer has nothing ready right now.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.queue.try_recv()
+    }
+
+    /// Blocks until a value is available, backing off from busy-spinning to
+    /// `thread::yield_now` and finally to short capped sleeps.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut round = 0;
+        loop {
+            match self.queue.try_recv() {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => backoff(&mut round),
+            }
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { rx: self }
+    }
+
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { rx: self }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+impl<'a, T> Iterator for TryIter<'a, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a BoundedReceiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> Clone for BoundedReceiver<T> {
+    fn clone(&self) -> Self {
+        self.queue.receivers.fetch_add(1, Ordering::SeqCst);
+        BoundedReceiver {
+            queue: Arc::clone(&self.queue),
+        }
+    }
+}
+
+impl<T> Drop for BoundedReceiver<T> {
+    fn drop(&mut self) {
+        self.queue.receivers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Creates a bounded, lock-free MPMC channel, returning a cloneable
+/// [`BoundedSender`] and [`BoundedReceiver`] pair.
+///
+/// `capacity` is rounded up to the next power of two, as required by the
+/// ring buffer's index masking.
+///
+/// [`BoundedSender`]: struct.BoundedSender.html
+/// [`BoundedReceiver`]: struct.BoundedReceiver.html
+pub fn bounded_shared_channel<T>(capacity: usize) -> (BoundedSender<T>, BoundedReceiver<T>) {
+    let queue = Arc::new(Queue::with_capacity(capacity));
+    queue.senders.store(1, Ordering::SeqCst);
+    queue.receivers.store(1, Ordering::SeqCst);
+    (
+        BoundedSender {
+            queue: Arc::clone(&queue),
+        },
+        BoundedReceiver { queue },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bounded_shared_channel;
+    use std::sync::mpsc::TrySendError;
+    use std::thread;
+
+    #[test]
+    fn smoke() {
+        let (tx, rx) = bounded_shared_channel::<i32>(4);
+        tx.send(1).unwrap();
+        assert_eq!(rx.recv().unwrap(), 1);
+    }
+
+    #[test]
+    fn try_send_full() {
+        let (tx, rx) = bounded_shared_channel::<i32>(2);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        match tx.try_send(3) {
+            Err(TrySendError::Full(3)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(rx.recv().unwrap(), 1);
+        assert_eq!(rx.recv().unwrap(), 2);
+    }
+
+    #[test]
+    fn smoke_port_gone() {
+        let (tx, rx) = bounded_shared_channel::<i32>(4);
+        drop(rx);
+        assert!(tx.send(1).is_err());
+    }
+
+    #[test]
+    fn smoke_chan_gone() {
+        let (tx, rx) = bounded_shared_channel::<i32>(4);
+        drop(tx);
+        assert!(rx.recv().is_err());
+    }
+
+    #[test]
+    fn stress_multi_receiver() {
+        const AMT: u32 = 10000;
+        const N_THREADS: u32 = 8;
+        let (tx, rx) = bounded_shared_channel::<i32>(64);
+
+        let mut workers = Vec::new();
+        for _ in 0..N_THREADS {
+            let rx = rx.clone();
+            workers.push(thread::spawn(move || {
+                let mut count = 0;
+                for _ in &rx {
+                    count += 1;
+                }
+                count
+            }));
+        }
+
+        for _ in 0..AMT * N_THREADS {
+            tx.send(1).unwrap();
+        }
+        drop(tx);
+
+        let mut count = 0;
+        for t in workers {
+            count += t.join().ok().unwrap();
+        }
+        assert_eq!(AMT * N_THREADS, count);
+    }
+}
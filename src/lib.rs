@@ -9,10 +9,23 @@
 //! * A function [`shared_channel`] corresponding to function `channel`
 //!   is defined. [`shared_channel`] returns a `(Sender, SharedReceiver)`
 //!   tuple instead of `(Sender, Receiver)` tuple.
+//! * A function [`shared_sync_channel`] corresponding to function
+//!   `sync_channel` is defined, giving a bounded/backpressured channel
+//!   whose receiving end is a [`SharedReceiver`].
 //! * Some feature of `std::sync::mpsc` is not implemented yet.
 //!
+//! With the `stream` cargo feature enabled, [`SharedReceiver`] additionally
+//! implements `futures_core::Stream`, so clones of a receiver can be driven
+//! from async tasks.
+//!
+//! For workloads where the `Mutex`-guarded [`SharedReceiver`] becomes a
+//! bottleneck under many concurrent consumers, the [`bounded`] module
+//! offers a lock-free alternative backed by a fixed-capacity ring buffer.
+//!
 //! [`SharedReceiver`]: struct.SharedReceiver.html
 //! [`shared_channel`]: fn.shared_channel.html
+//! [`shared_sync_channel`]: fn.shared_sync_channel.html
+//! [`bounded`]: bounded/index.html
 //!
 //! # Example
 //!
@@ -37,29 +50,71 @@
 //!
 //! More examples, see examples directory.
 
-use std::sync::mpsc::{channel, Receiver, RecvError, Sender, TryRecvError};
+pub mod bounded;
+
+pub use bounded::{bounded_shared_channel, BoundedReceiver, BoundedSender};
+
+use std::cell::Cell;
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+#[cfg(feature = "stream")]
+use std::sync::mpsc::SendError;
+#[cfg(not(feature = "stream"))]
+use std::sync::mpsc::Sender;
+#[cfg(feature = "stream")]
+use std::sync::mpsc::Sender as StdSender;
+#[cfg(not(feature = "stream"))]
+use std::sync::mpsc::SyncSender;
+#[cfg(feature = "stream")]
+use std::sync::mpsc::SyncSender as StdSyncSender;
+use std::sync::mpsc::{channel, sync_channel, Receiver, RecvError, RecvTimeoutError, TryRecvError};
+#[cfg(feature = "stream")]
+use std::sync::mpsc::TrySendError;
 use std::sync::{Arc, Mutex, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll, Waker};
 
 pub struct SharedReceiver<T> {
     inner: Arc<Mutex<Receiver<T>>>,
+    #[cfg(feature = "stream")]
+    wakers: Arc<Mutex<Vec<Waker>>>,
 }
 
 pub struct Iter<'a, T: 'a> {
     rx: &'a SharedReceiver<T>,
 }
 
+pub struct TryIter<'a, T: 'a> {
+    rx: &'a SharedReceiver<T>,
+}
+
 impl<T> Clone for SharedReceiver<T> {
     fn clone(&self) -> Self {
         SharedReceiver {
             inner: Arc::clone(&self.inner),
+            #[cfg(feature = "stream")]
+            wakers: Arc::clone(&self.wakers),
         }
     }
 }
 
 impl<T> SharedReceiver<T> {
+    #[cfg(not(feature = "stream"))]
+    fn new(receiver: Receiver<T>) -> SharedReceiver<T> {
+        SharedReceiver {
+            inner: Arc::new(Mutex::new(receiver)),
+        }
+    }
+
+    #[cfg(feature = "stream")]
     fn new(receiver: Receiver<T>) -> SharedReceiver<T> {
         SharedReceiver {
             inner: Arc::new(Mutex::new(receiver)),
+            wakers: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -78,9 +133,57 @@ impl<T> SharedReceiver<T> {
         }
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    /// Waits for a value on this receiver, timing out after `timeout` has
+    /// elapsed.
+    ///
+    /// Because the underlying `Receiver` is shared behind a `Mutex`, the
+    /// timeout also bounds the time spent waiting to acquire the lock.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_deadline(Instant::now() + timeout)
+    }
+
+    /// Waits for a value on this receiver, timing out once `deadline` has
+    /// passed.
+    ///
+    /// See [`recv_timeout`] for details on how the deadline interacts with
+    /// the shared lock.
+    ///
+    /// [`recv_timeout`]: #method.recv_timeout
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        loop {
+            match self.inner.try_lock() {
+                Ok(mutex) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    return mutex.recv_timeout(remaining);
+                }
+                Err(TryLockError::Poisoned(_)) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryLockError::WouldBlock) => {
+                    if Instant::now() >= deadline {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                    thread::sleep(Duration::from_micros(50));
+                }
+            }
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter { rx: self }
     }
+
+    /// Returns a non-blocking iterator over currently available values.
+    ///
+    /// Unlike [`iter`], which blocks on [`recv`] and so never ends while any
+    /// sender is alive, `TryIter::next` calls [`try_recv`] and yields `None`
+    /// as soon as the channel is momentarily empty (or the shared lock is
+    /// contended), letting a consumer drain what's available and move on.
+    ///
+    /// [`iter`]: #method.iter
+    /// [`recv`]: #method.recv
+    /// [`try_recv`]: #method.try_recv
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { rx: self }
+    }
 }
 
 impl<'a, T> Iterator for Iter<'a, T> {
@@ -90,6 +193,13 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
+impl<'a, T> Iterator for TryIter<'a, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+}
+
 impl<'a, T> IntoIterator for &'a SharedReceiver<T> {
     type Item = T;
     type IntoIter = Iter<'a, T>;
@@ -99,15 +209,281 @@ impl<'a, T> IntoIterator for &'a SharedReceiver<T> {
     }
 }
 
+#[cfg(not(feature = "stream"))]
 pub fn shared_channel<T>() -> (Sender<T>, SharedReceiver<T>) {
     let (sender, receiver) = channel();
     (sender, SharedReceiver::new(receiver))
 }
 
+#[cfg(feature = "stream")]
+pub fn shared_channel<T>() -> (Sender<T>, SharedReceiver<T>) {
+    let (sender, receiver) = channel();
+    let receiver = SharedReceiver::new(receiver);
+    let wakers = Arc::clone(&receiver.wakers);
+    (
+        Sender {
+            inner: sender,
+            wakers,
+        },
+        receiver,
+    )
+}
+
+/// Wakes every task currently parked on a `Stream`-polled [`SharedReceiver`]
+/// sharing this waker list, then empties it.
+///
+/// Draining and waking all of them (rather than popping a single one) keeps
+/// the list from growing without bound under steady polling and makes sure
+/// every clone of a `SharedReceiver`, not just the most recently parked one,
+/// gets a chance to re-poll.
+///
+/// [`SharedReceiver`]: struct.SharedReceiver.html
+#[cfg(feature = "stream")]
+fn wake_all(wakers: &Mutex<Vec<Waker>>) {
+    for waker in wakers.lock().unwrap().drain(..) {
+        waker.wake();
+    }
+}
+
+/// A handle for sending values to a [`SharedReceiver`], available under the
+/// `stream` cargo feature.
+///
+/// Wraps `std::sync::mpsc::Sender` so that each successful `send` can wake
+/// the tasks parked on the receiving [`SharedReceiver`]'s `Stream`
+/// implementation.
+///
+/// [`SharedReceiver`]: struct.SharedReceiver.html
+#[cfg(feature = "stream")]
+pub struct Sender<T> {
+    inner: StdSender<T>,
+    wakers: Arc<Mutex<Vec<Waker>>>,
+}
+
+#[cfg(feature = "stream")]
+impl<T> Sender<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let result = self.inner.send(t);
+        if result.is_ok() {
+            wake_all(&self.wakers);
+        }
+        result
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender {
+            inner: self.inner.clone(),
+            wakers: Arc::clone(&self.wakers),
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T> Stream for SharedReceiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        match self.try_recv() {
+            Ok(value) => return Poll::Ready(Some(value)),
+            Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+            Err(TryRecvError::Empty) => {}
+        }
+        // Register before re-checking: a `send` landing between the first
+        // `try_recv` and this push would otherwise wake nobody, since the
+        // waker wasn't registered yet, leaving the task parked forever.
+        self.wakers.lock().unwrap().push(cx.waker().clone());
+        match self.try_recv() {
+            Ok(value) => {
+                // A value showed up between the two checks: drop the waker
+                // we just registered instead of leaving it to linger in the
+                // list until some future `send` drains it.
+                self.wakers.lock().unwrap().retain(|w| !w.will_wake(cx.waker()));
+                Poll::Ready(Some(value))
+            }
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+}
+
+/// Creates a bounded, synchronous channel whose receiving end is a
+/// [`SharedReceiver`], corresponding to `std::sync::mpsc::sync_channel`.
+///
+/// `bound` is the maximum number of messages that can be buffered before
+/// `SyncSender::send` blocks the caller, giving producers backpressure
+/// instead of growing an unbounded in-memory queue.
+///
+/// [`SharedReceiver`]: struct.SharedReceiver.html
+#[cfg(not(feature = "stream"))]
+pub fn shared_sync_channel<T>(bound: usize) -> (SyncSender<T>, SharedReceiver<T>) {
+    let (sender, receiver) = sync_channel(bound);
+    (sender, SharedReceiver::new(receiver))
+}
+
+/// Creates a bounded, synchronous channel whose receiving end is a
+/// [`SharedReceiver`], corresponding to `std::sync::mpsc::sync_channel`.
+///
+/// `bound` is the maximum number of messages that can be buffered before
+/// `SyncSender::send` blocks the caller, giving producers backpressure
+/// instead of growing an unbounded in-memory queue.
+///
+/// [`SharedReceiver`]: struct.SharedReceiver.html
+#[cfg(feature = "stream")]
+pub fn shared_sync_channel<T>(bound: usize) -> (SyncSender<T>, SharedReceiver<T>) {
+    let (sender, receiver) = sync_channel(bound);
+    let receiver = SharedReceiver::new(receiver);
+    let wakers = Arc::clone(&receiver.wakers);
+    (
+        SyncSender {
+            inner: sender,
+            wakers,
+        },
+        receiver,
+    )
+}
+
+/// A handle for sending values to a [`SharedReceiver`] backed by
+/// [`shared_sync_channel`], available under the `stream` cargo feature.
+///
+/// Wraps `std::sync::mpsc::SyncSender` so that each successful `send`/
+/// `try_send` can wake the tasks parked on the receiving
+/// [`SharedReceiver`]'s `Stream` implementation, the same as [`Sender`]
+/// does for [`shared_channel`].
+///
+/// [`SharedReceiver`]: struct.SharedReceiver.html
+/// [`shared_sync_channel`]: fn.shared_sync_channel.html
+/// [`shared_channel`]: fn.shared_channel.html
+#[cfg(feature = "stream")]
+pub struct SyncSender<T> {
+    inner: StdSyncSender<T>,
+    wakers: Arc<Mutex<Vec<Waker>>>,
+}
+
+#[cfg(feature = "stream")]
+impl<T> SyncSender<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let result = self.inner.send(t);
+        if result.is_ok() {
+            wake_all(&self.wakers);
+        }
+        result
+    }
+
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        let result = self.inner.try_send(t);
+        if result.is_ok() {
+            wake_all(&self.wakers);
+        }
+        result
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T> Clone for SyncSender<T> {
+    fn clone(&self) -> Self {
+        SyncSender {
+            inner: self.inner.clone(),
+            wakers: Arc::clone(&self.wakers),
+        }
+    }
+}
+
+/// Number of busy-spin polls to try before backing off to `thread::yield_now`.
+const SELECT_SPIN_LIMIT: u32 = 50;
+/// Number of `thread::yield_now` polls to try before backing off to sleeping.
+const SELECT_YIELD_LIMIT: u32 = 100;
+/// Upper bound on the sleep used while backing off between poll rounds.
+const SELECT_MAX_BACKOFF: Duration = Duration::from_millis(4);
+
+/// A builder for waiting on the first of several [`SharedReceiver`]s to
+/// become ready, similar in spirit to crossbeam's `select!`.
+///
+/// Register receivers with [`recv`], then call [`ready`] to block until one
+/// of them yields a value, getting back the index of the chosen receiver
+/// together with the item.
+///
+/// [`SharedReceiver`]: struct.SharedReceiver.html
+/// [`recv`]: #method.recv
+/// [`ready`]: #method.ready
+pub struct Select<'a, T> {
+    receivers: Vec<&'a SharedReceiver<T>>,
+    next: Cell<usize>,
+}
+
+impl<'a, T> Default for Select<'a, T> {
+    fn default() -> Select<'a, T> {
+        Select::new()
+    }
+}
+
+impl<'a, T> Select<'a, T> {
+    pub fn new() -> Select<'a, T> {
+        Select {
+            receivers: Vec::new(),
+            next: Cell::new(0),
+        }
+    }
+
+    /// Registers a receiver, returning `self` so registrations can be
+    /// chained.
+    pub fn recv(mut self, rx: &'a SharedReceiver<T>) -> Select<'a, T> {
+        self.receivers.push(rx);
+        self
+    }
+
+    /// Blocks until one of the registered receivers has a value ready,
+    /// returning its index (in registration order) together with the item.
+    ///
+    /// Polls the registered receivers in rotation so no single receiver is
+    /// starved, backing off from busy-spinning to `thread::yield_now` and
+    /// finally to short sleeps (capped at a few milliseconds) the longer it
+    /// waits. Returns `Err(RecvError)` once every registered receiver is
+    /// disconnected.
+    pub fn ready(&self) -> Result<(usize, T), RecvError> {
+        let n = self.receivers.len();
+        if n == 0 {
+            return Err(RecvError);
+        }
+        let mut round = 0u32;
+        loop {
+            let start = self.next.get();
+            self.next.set((start + 1) % n);
+
+            let mut disconnected = 0;
+            for offset in 0..n {
+                let idx = (start + offset) % n;
+                match self.receivers[idx].try_recv() {
+                    Ok(value) => return Ok((idx, value)),
+                    Err(TryRecvError::Disconnected) => disconnected += 1,
+                    Err(TryRecvError::Empty) => {}
+                }
+            }
+            if disconnected == n {
+                return Err(RecvError);
+            }
+
+            if round < SELECT_SPIN_LIMIT {
+                // busy spin
+            } else if round < SELECT_YIELD_LIMIT {
+                thread::yield_now();
+            } else {
+                let backoff =
+                    Duration::from_micros(u64::from(round - SELECT_YIELD_LIMIT) * 100);
+                thread::sleep(backoff.min(SELECT_MAX_BACKOFF));
+            }
+            round += 1;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::shared_channel;
+    use super::{shared_channel, shared_sync_channel, Select};
+    use std::sync::mpsc::{RecvTimeoutError, TrySendError};
     use std::thread;
+    use std::time::Duration;
 
     #[test]
     fn smoke() {
@@ -306,6 +682,111 @@ mod tests {
         assert_eq!(AMT * (AMT + 1) / 2 * N_SENDER, sum);
     }
 
+    #[test]
+    fn smoke_recv_timeout() {
+        let (tx, rx) = shared_channel::<i32>();
+        tx.send(1).unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_millis(100)).unwrap(), 1);
+    }
+
+    #[test]
+    fn recv_timeout_times_out() {
+        let (_tx, rx) = shared_channel::<i32>();
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    }
+
+    #[test]
+    fn recv_timeout_disconnected() {
+        let (tx, rx) = shared_channel::<i32>();
+        drop(tx);
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn smoke_sync() {
+        let (tx, rx) = shared_sync_channel::<i32>(1);
+        tx.send(1).unwrap();
+        assert_eq!(rx.recv().unwrap(), 1);
+    }
+
+    #[test]
+    fn smoke_sync_multi_receiver() {
+        let (tx, rx) = shared_sync_channel::<i32>(2);
+        let rx2 = rx.clone();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx.recv().unwrap(), 1);
+        assert_eq!(rx2.recv().unwrap(), 2);
+    }
+
+    #[test]
+    fn sync_try_send_full() {
+        let (tx, rx) = shared_sync_channel::<i32>(1);
+        tx.send(1).unwrap();
+        match tx.try_send(2) {
+            Err(TrySendError::Full(2)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(rx.recv().unwrap(), 1);
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn stream_yields_sent_values() {
+        use futures::executor::block_on;
+        use futures::stream::StreamExt;
+
+        let (tx, rx) = shared_channel::<i32>();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        drop(tx);
+
+        let items: Vec<i32> = block_on(rx.collect());
+        assert_eq!(items, vec![1, 2]);
+    }
+
+    #[test]
+    fn select_picks_ready_receiver() {
+        let (tx1, rx1) = shared_channel::<i32>();
+        let (_tx2, rx2) = shared_channel::<i32>();
+        tx1.send(42).unwrap();
+
+        let select = Select::new().recv(&rx1).recv(&rx2);
+        assert_eq!(select.ready().unwrap(), (0, 42));
+    }
+
+    #[test]
+    fn select_waits_for_a_sender() {
+        let (tx1, rx1) = shared_channel::<i32>();
+        let (tx2, rx2) = shared_channel::<i32>();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            tx2.send(7).unwrap();
+            let _tx1 = tx1;
+        });
+
+        let select = Select::new().recv(&rx1).recv(&rx2);
+        assert_eq!(select.ready().unwrap(), (1, 7));
+        t.join().unwrap();
+    }
+
+    #[test]
+    fn select_all_disconnected() {
+        let (tx1, rx1) = shared_channel::<i32>();
+        let (tx2, rx2) = shared_channel::<i32>();
+        drop(tx1);
+        drop(tx2);
+
+        let select = Select::new().recv(&rx1).recv(&rx2);
+        assert!(select.ready().is_err());
+    }
+
     #[test]
     fn smoke_try_recv() {
         let (tx, rx) = shared_channel::<i32>();
@@ -326,4 +807,14 @@ mod tests {
         }
         t.join().ok().unwrap();
     }
+
+    #[test]
+    fn smoke_try_iter() {
+        let (tx, rx) = shared_channel::<i32>();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        let items: Vec<i32> = rx.try_iter().collect();
+        assert_eq!(items, vec![1, 2]);
+        assert_eq!(rx.try_iter().next(), None);
+    }
 }